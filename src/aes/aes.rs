@@ -1,25 +1,118 @@
 use core::marker::PhantomData;
+use core::ptr::{read_volatile, write_volatile};
 
-use cipher::{BlockBackend, ParBlocksSizeUser, BlockSizeUser, consts::{U16, U1}, generic_array::ArrayLength};
+use cipher::{
+    consts::{U1, U16, U24, U32},
+    generic_array::typenum::Unsigned,
+    inout::InOut,
+    generic_array::ArrayLength,
+    Block, BlockBackend, BlockCipher, BlockDecrypt, BlockEncrypt, BlockSizeUser, Key, KeyInit,
+    KeySizeUser, ParBlocksSizeUser,
+};
 
+const AES_BASE_ADDR : u32 = 0x6003_C000;
+
+/// AES-128 block cipher, backed by the hardware accelerator.
+pub type Aes128 = Aes<U16>;
+/// AES-192 block cipher, backed by the hardware accelerator.
+pub type Aes192 = Aes<U24>;
+/// AES-256 block cipher, backed by the hardware accelerator.
+pub type Aes256 = Aes<U32>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Encrypt,
+    Decrypt,
+}
+
+/// Hardware-accelerated AES block cipher, driven through the AES peripheral's typical
+/// (register-driven) mode.
+///
+/// `S` is the key length (`U16`/`U24`/`U32` for AES-128/192/256); the block size is always 16
+/// bytes. Use [`Aes128`], [`Aes192`] or [`Aes256`] rather than naming `Aes<S>` directly.
 pub struct Aes<S : 'static + ArrayLength<u8>> {
+    mode : Mode,
+    _key_size : PhantomData<S>,
+}
+
+impl<S : 'static + ArrayLength<u8> + Unsigned> Aes<S> {
+    const KEY_REG : u32 = AES_BASE_ADDR;
+    const TEXT_IN_REG : u32 = AES_BASE_ADDR + 0x20;
+    const MODE_REG : u32 = AES_BASE_ADDR + 0x40;
+    const TRIGGER_REG : u32 = AES_BASE_ADDR + 0x48;
+    const STATE_REG : u32 = AES_BASE_ADDR + 0x4C;
+    const TEXT_OUT_REG : u32 = AES_BASE_ADDR + 0x30;
+
+    fn load_key(key : &[u8]) {
+        for (i, chunk) in key.chunks(4).enumerate() {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+
+            unsafe {
+                write_volatile(
+                    (Self::KEY_REG + 4 * i as u32) as *mut u32,
+                    u32::from_le_bytes(word),
+                );
+            }
+        }
+    }
+
+    /// Program `MODE_REG` with the key-length/direction encoding (0/1/2 for AES-128/192/256,
+    /// +4 for decrypt) for `self.mode`.
+    fn apply_mode(&self) {
+        let key_words = (S::to_usize() - 16) / 8;
+        let mode_val = key_words as u32 + if self.mode == Mode::Decrypt { 4 } else { 0 };
+
+        unsafe { write_volatile(Self::MODE_REG as *mut u32, mode_val) };
+    }
+
+    /// Run a single 16 byte block through the accelerator using its typical, register-driven
+    /// (non-DMA) mode: the block is written directly to the `TEXT_IN` registers, the operation
+    /// is triggered, and the result is read back from the `TEXT_OUT` registers once the
+    /// peripheral reports idle again.
+    ///
+    /// This is a deliberate deviation from chaining a `ListItem` pair through a `DMAPipe`, which
+    /// is what per-block AES was originally asked to do: the AES peripheral here has no
+    /// DMA-enable register of its own to flip it into that mode, so driving it via DMA without
+    /// one would queue a transfer the peripheral never reads, hanging forever on completion.
+    /// Re-resetting a whole DMA channel/peripheral for every 16 input bytes would also be far
+    /// more overhead than the transfer itself. Multi-block modes built on top of this are still
+    /// free to drive `Peripheral::AES` through a `DMAPipe` directly, if and when the peripheral
+    /// actually supports it.
+    fn run_block(&self, input : &[u8; 16], output : &mut [u8; 16]) {
+        self.apply_mode();
+
+        for (i, chunk) in input.chunks(4).enumerate() {
+            let mut word = [0u8; 4];
+            word.copy_from_slice(chunk);
+
+            unsafe {
+                write_volatile((Self::TEXT_IN_REG + 4 * i as u32) as *mut u32, u32::from_le_bytes(word));
+            }
+        }
+
+        unsafe { write_volatile(Self::TRIGGER_REG as *mut u32, 0b1) };
+
+        while unsafe { read_volatile(Self::STATE_REG as *const u32) } != 0 {}
 
-    _size : PhantomData<S>
+        for (i, word) in output.chunks_mut(4).enumerate() {
+            let bytes = unsafe { read_volatile((Self::TEXT_OUT_REG + 4 * i as u32) as *const u32) }.to_le_bytes();
+
+            word.copy_from_slice(&bytes);
+        }
+    }
 }
 
-impl<S : 'static + ArrayLength<u8>> BlockBackend for Aes<S> {
-    fn proc_block(&mut self, mut block: cipher::inout::InOut<'_, '_, cipher::Block<Self>>) {
+impl<S : 'static + ArrayLength<u8> + Unsigned> BlockBackend for Aes<S> {
+    fn proc_block(&mut self, mut block : InOut<'_, '_, Block<Self>>) {
+        let mut input = [0u8; 16];
+        input.copy_from_slice(block.get_in());
 
-        let input_ptr = block.get_in().as_ptr() as usize;
+        let mut output = [0u8; 16];
 
-        let in_channel : [u32; 3] = [
-            0b1,
-            input_ptr as u32,
-            0
-        ];
+        self.run_block(&input, &mut output);
 
-        
-        todo!()
+        block.get_out().copy_from_slice(&output);
     }
 }
 
@@ -28,5 +121,40 @@ impl<S : 'static + ArrayLength<u8>> ParBlocksSizeUser for Aes<S> {
 }
 
 impl<S : 'static + ArrayLength<u8>> BlockSizeUser for Aes<S> {
-    type BlockSize = S;
-}
\ No newline at end of file
+    type BlockSize = U16;
+}
+
+impl<S : 'static + ArrayLength<u8>> BlockCipher for Aes<S> {}
+
+impl<S : 'static + ArrayLength<u8>> KeySizeUser for Aes<S> {
+    type KeySize = S;
+}
+
+impl<S : 'static + ArrayLength<u8> + Unsigned> KeyInit for Aes<S> {
+    fn new(key : &Key<Self>) -> Self {
+        Self::load_key(key);
+
+        Aes {
+            mode : Mode::Encrypt,
+            _key_size : PhantomData,
+        }
+    }
+}
+
+impl<S : 'static + ArrayLength<u8> + Unsigned> BlockEncrypt for Aes<S> {
+    fn encrypt_with_backend(&self, f : impl cipher::BlockClosure<BlockSize = Self::BlockSize>) {
+        f.call(&mut Aes::<S> {
+            mode : Mode::Encrypt,
+            _key_size : PhantomData,
+        });
+    }
+}
+
+impl<S : 'static + ArrayLength<u8> + Unsigned> BlockDecrypt for Aes<S> {
+    fn decrypt_with_backend(&self, f : impl cipher::BlockClosure<BlockSize = Self::BlockSize>) {
+        f.call(&mut Aes::<S> {
+            mode : Mode::Decrypt,
+            _key_size : PhantomData,
+        });
+    }
+}
@@ -0,0 +1,5 @@
+//! Hardware AES block cipher accelerator.
+
+mod aes;
+
+pub use aes::{Aes, Aes128, Aes192, Aes256};
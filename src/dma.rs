@@ -1,7 +1,9 @@
 //! Driver for DMA hardware
 
 use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{compiler_fence, Ordering};
 
+use embedded_dma::{ReadBuffer, WriteBuffer};
 use esp32c3::{Peripherals, DMA};
 
 /// Item in a list of DMA transfers
@@ -41,6 +43,14 @@ impl ListItem {
         self.next_item_ptr = next_ptr as u32;
     }
 
+    /// Link this item to `next` without clearing the owner/EOF bits, so the hardware treats the
+    /// descriptor as still owned and re-reads it after wrapping back around to it. Used to
+    /// build self-refilling circular DMA rings.
+    pub fn set_next_circular(&mut self, next : *mut ListItem) {
+        let next_ptr = next as usize;
+        self.next_item_ptr = next_ptr as u32;
+    }
+
     /// Check if the error bit was set by hardware.
     pub fn has_error(&self) -> bool {
         (self.state >> 28) != 0
@@ -205,6 +215,70 @@ impl Channel {
         unsafe { write_volatile(out_link_reg, out_link)};
     }
 
+    /// Enable the transmit completion (`out_eof`) interrupt for this channel.
+    pub fn enable_tx_interrupt(&self, dma : &DMA) {
+        match self {
+            Channel::Channel0 => dma.int_ena_ch0.modify(|_, w| w.out_eof_ch0_int_ena().set_bit()),
+            Channel::Channel1 => dma.int_ena_ch1.modify(|_, w| w.out_eof_ch1_int_ena().set_bit()),
+            Channel::Channel2 => dma.int_ena_ch2.modify(|_, w| w.out_eof_ch2_int_ena().set_bit()),
+        }
+    }
+
+    /// Disable the transmit completion (`out_eof`) interrupt for this channel.
+    pub fn disable_tx_interrupt(&self, dma : &DMA) {
+        match self {
+            Channel::Channel0 => dma.int_ena_ch0.modify(|_, w| w.out_eof_ch0_int_ena().clear_bit()),
+            Channel::Channel1 => dma.int_ena_ch1.modify(|_, w| w.out_eof_ch1_int_ena().clear_bit()),
+            Channel::Channel2 => dma.int_ena_ch2.modify(|_, w| w.out_eof_ch2_int_ena().clear_bit()),
+        }
+    }
+
+    /// Enable the receive completion (`in_done`) interrupt for this channel.
+    pub fn enable_rx_interrupt(&self, dma : &DMA) {
+        match self {
+            Channel::Channel0 => dma.int_ena_ch0.modify(|_, w| w.in_done_ch0_int_ena().set_bit()),
+            Channel::Channel1 => dma.int_ena_ch1.modify(|_, w| w.in_done_ch1_int_ena().set_bit()),
+            Channel::Channel2 => dma.int_ena_ch2.modify(|_, w| w.in_done_ch2_int_ena().set_bit()),
+        }
+    }
+
+    /// Disable the receive completion (`in_done`) interrupt for this channel.
+    pub fn disable_rx_interrupt(&self, dma : &DMA) {
+        match self {
+            Channel::Channel0 => dma.int_ena_ch0.modify(|_, w| w.in_done_ch0_int_ena().clear_bit()),
+            Channel::Channel1 => dma.int_ena_ch1.modify(|_, w| w.in_done_ch1_int_ena().clear_bit()),
+            Channel::Channel2 => dma.int_ena_ch2.modify(|_, w| w.in_done_ch2_int_ena().clear_bit()),
+        }
+    }
+
+    /// Check whether this channel's transmit completion (`out_eof`) interrupt flag is set.
+    pub fn is_out_eof_interrupt_set(&self, dma : &DMA) -> bool {
+        match self {
+            Channel::Channel0 => dma.int_st_ch0.read().out_eof_ch0_int_st().bit_is_set(),
+            Channel::Channel1 => dma.int_st_ch1.read().out_eof_ch1_int_st().bit_is_set(),
+            Channel::Channel2 => dma.int_st_ch2.read().out_eof_ch2_int_st().bit_is_set(),
+        }
+    }
+
+    /// Clear this channel's transmit completion (`out_eof`) interrupt flag.
+    pub fn reset_out_eof_interrupt(&self, dma : &DMA) {
+        match self {
+            Channel::Channel0 => dma.int_clr_ch0.write(|w| w.out_eof_ch0_int_clr().set_bit()),
+            Channel::Channel1 => dma.int_clr_ch1.write(|w| w.out_eof_ch1_int_clr().set_bit()),
+            Channel::Channel2 => dma.int_clr_ch2.write(|w| w.out_eof_ch2_int_clr().set_bit()),
+        }
+    }
+
+    /// Read the address of the descriptor the receive side of the channel is currently (or most
+    /// recently was) processing, used to compute progress through a circular DMA ring.
+    pub fn rx_current_descriptor(&self, dma : &DMA) -> u32 {
+        match self {
+            Channel::Channel0 => dma.in_dscr_bf0_ch0.read().bits(),
+            Channel::Channel1 => dma.in_dscr_bf0_ch1.read().bits(),
+            Channel::Channel2 => dma.in_dscr_bf0_ch2.read().bits(),
+        }
+    }
+
     /// Enable memory to memory transfer (only possible on rx part of channel)
     pub fn mem_to_mem(&self, dma : &DMA) {
         let conf0_reg = match self {
@@ -288,4 +362,283 @@ impl DMAPipe {
             Channel::Channel2 => self.dma.int_st_ch2.read().in_done_ch2_int_st().bit_is_set(),
         }
     }
+
+    /// Enable the transmit completion interrupt, so completion can be serviced from the DMA
+    /// interrupt handler instead of by polling [`DMAPipe::get_tx_completion`].
+    pub fn enable_tx_interrupt(&self) {
+        self.tx_channel.enable_tx_interrupt(&self.dma);
+    }
+
+    /// Disable the transmit completion interrupt.
+    pub fn disable_tx_interrupt(&self) {
+        self.tx_channel.disable_tx_interrupt(&self.dma);
+    }
+
+    /// Enable the receive completion interrupt, so completion can be serviced from the DMA
+    /// interrupt handler instead of by polling [`DMAPipe::get_rx_completion`].
+    pub fn enable_rx_interrupt(&self) {
+        self.rx_channel.enable_rx_interrupt(&self.dma);
+    }
+
+    /// Disable the receive completion interrupt.
+    pub fn disable_rx_interrupt(&self) {
+        self.rx_channel.disable_rx_interrupt(&self.dma);
+    }
+
+    /// Check whether the transmit completion interrupt flag is set.
+    pub fn is_out_eof_interrupt_set(&self) -> bool {
+        self.tx_channel.is_out_eof_interrupt_set(&self.dma)
+    }
+
+    /// Clear the transmit completion interrupt flag from within the interrupt handler.
+    pub fn reset_out_eof_interrupt(&self) {
+        self.tx_channel.reset_out_eof_interrupt(&self.dma);
+    }
+}
+
+/// An in-progress DMA transfer that owns its source/destination buffers until completion,
+/// preventing them from being dropped or mutated while the DMA engine is reading or writing
+/// them.
+///
+/// `tx_item`/`rx_item` must be `'static` descriptor storage: the DMA engine reads them for as
+/// long as the transfer is running, so they cannot live on a stack frame that might be unwound
+/// or reused. This is enforced in the type itself (rather than just documented) because a caller
+/// could otherwise skip the blocking [`Drop`] guard below with `core::mem::forget` and let
+/// shorter-lived descriptor storage go out of scope while the DMA engine still holds pointers
+/// into it; a `&'static mut ListItem` can only ever come from storage nothing can deallocate.
+pub struct Transfer<TX, RX> {
+    tx_buffer : TX,
+    rx_buffer : RX,
+    tx_item : &'static mut ListItem,
+    rx_item : &'static mut ListItem,
+    pipe : DMAPipe,
+}
+
+impl<TX, RX> Transfer<TX, RX>
+where
+    TX : ReadBuffer<Word = u8>,
+    RX : WriteBuffer<Word = u8>,
+{
+    /// Start a transfer between `tx_buffer` and `rx_buffer` over `pipe`, taking ownership of the
+    /// buffers for the duration of the transfer.
+    pub fn start(
+        mut pipe : DMAPipe,
+        tx_buffer : TX,
+        mut rx_buffer : RX,
+        tx_item : &'static mut ListItem,
+        rx_item : &'static mut ListItem,
+    ) -> Self {
+        let (tx_ptr, tx_len) = unsafe { tx_buffer.read_buffer() };
+        let (rx_ptr, rx_len) = unsafe { rx_buffer.write_buffer() };
+
+        *tx_item = ListItem::new();
+        tx_item.set_buffer::<u8>(unsafe { core::slice::from_raw_parts_mut(tx_ptr as *mut u8, tx_len) });
+
+        *rx_item = ListItem::new();
+        rx_item.set_buffer::<u8>(unsafe { core::slice::from_raw_parts_mut(rx_ptr, rx_len) });
+
+        // The descriptors above must be visible to the DMA engine before we enable the channels.
+        compiler_fence(Ordering::SeqCst);
+
+        pipe.start_transfer(tx_item, rx_item);
+
+        Transfer { tx_buffer, rx_buffer, tx_item, rx_item, pipe }
+    }
+
+    /// Block until the transfer completes, then release the buffers and the pipe back to the
+    /// caller.
+    pub fn wait(self) -> (TX, RX, DMAPipe) {
+        while !self.is_done() {}
+
+        // Make sure the buffer contents written by the DMA engine are visible before we hand
+        // `rx_buffer` back to the caller.
+        compiler_fence(Ordering::SeqCst);
+
+        // `Drop` also blocks on completion; skip running it so it doesn't wait a second time on
+        // a transfer we already know is done.
+        let transfer = core::mem::ManuallyDrop::new(self);
+
+        unsafe {
+            (
+                core::ptr::read(&transfer.tx_buffer),
+                core::ptr::read(&transfer.rx_buffer),
+                core::ptr::read(&transfer.pipe),
+            )
+        }
+    }
+}
+
+impl<TX, RX> Transfer<TX, RX> {
+    /// Check whether the transfer has completed, without blocking.
+    pub fn is_done(&self) -> bool {
+        self.pipe.get_tx_completion() && self.pipe.get_rx_completion()
+    }
+}
+
+impl<TX, RX> Drop for Transfer<TX, RX> {
+    /// Block until the DMA engine reports completion before the buffers can be dropped or
+    /// reused, so a caller letting a `Transfer` fall out of scope (panic, early return, or simply
+    /// not binding it) can never free memory the DMA engine still holds pointers into.
+    fn drop(&mut self) {
+        while !self.is_done() {}
+
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Error produced while reading from a [`CircularReader`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CircularError {
+    /// The DMA engine has wrapped around and overwritten chunks that had not yet been read.
+    Overrun,
+    /// `out` was shorter than a single chunk, so it could not hold one without silently
+    /// discarding the rest of it.
+    OutputTooShort,
+}
+
+/// A ring of `ListItem` descriptors over a buffer, linked so the last descriptor's
+/// `next_item_ptr` points back at the first, letting the DMA engine refill it forever without
+/// CPU intervention.
+pub struct CircularBuffer<'a> {
+    items : &'a mut [ListItem],
+    chunk_len : usize,
+}
+
+impl<'a> CircularBuffer<'a> {
+    /// Split `buffer` into `items.len()` equally sized chunks and link `items` into a loop over
+    /// them.
+    ///
+    /// `buffer` is tied to the same `'a` as `items`: each `ListItem` points straight at its slice
+    /// of `buffer`, so the backing memory must live at least as long as the descriptors do, not
+    /// just as long as this call.
+    pub fn new(items : &'a mut [ListItem], buffer : &'a mut [u8]) -> Self {
+        let chunk_len = buffer.len() / items.len();
+
+        for (item, chunk) in items.iter_mut().zip(buffer.chunks_mut(chunk_len)) {
+            *item = ListItem::new();
+            item.set_buffer::<u8>(chunk);
+        }
+
+        for i in 0..items.len() {
+            let next = (i + 1) % items.len();
+            let next_ptr : *mut ListItem = &mut items[next];
+            items[i].set_next_circular(next_ptr);
+        }
+
+        CircularBuffer { items, chunk_len }
+    }
+}
+
+/// A reader over a [`CircularBuffer`] that has been handed to the DMA engine for continuous
+/// streaming (e.g. ADC or UART input), tracking how far the engine has advanced by polling the
+/// channel's current descriptor address.
+///
+/// Progress is tracked as a pair of monotonic, unwrapped counters (`write_pos`/`read_pos`)
+/// instead of a bare ring index, so a write pointer that has lapped the reader by one or more
+/// full revolutions is distinguishable from "nothing new yet" rather than aliasing back onto
+/// `read_pos`. This does assume [`CircularReader::available`]/[`CircularReader::read`] are
+/// called at least once per revolution of the ring; whole revolutions that pass between two polls
+/// are dropped from the count, same as with any other ring-index-based tracker.
+pub struct CircularReader<'a> {
+    pipe : DMAPipe,
+    items : &'a mut [ListItem],
+    chunk_len : usize,
+    last_raw_index : usize,
+    write_pos : usize,
+    read_pos : usize,
+}
+
+impl<'a> CircularReader<'a> {
+    /// Start a continuously self-refilling receive transfer into `ring`.
+    pub fn start(pipe : DMAPipe, ring : CircularBuffer<'a>) -> Self {
+        let CircularBuffer { items, chunk_len } = ring;
+
+        let head : *const ListItem = &items[0];
+
+        pipe.rx_channel.set_rx_start(&pipe.dma, head);
+        pipe.rx_channel.rx_enable(&pipe.dma);
+
+        CircularReader {
+            pipe,
+            items,
+            chunk_len,
+            last_raw_index : 0,
+            write_pos : 0,
+            read_pos : 0,
+        }
+    }
+
+    /// Index of the descriptor the DMA engine is currently (or most recently was) writing to.
+    fn current_item(&self) -> usize {
+        let current = self.pipe.rx_channel.rx_current_descriptor(&self.pipe.dma) as usize & 0xFFFFF;
+
+        self.items
+            .iter()
+            .position(|item| (item as *const ListItem as usize) & 0xFFFFF == current)
+            .unwrap_or(self.last_raw_index)
+    }
+
+    /// Advance `write_pos` by however many descriptors the engine has moved through since the
+    /// last poll, converting the wrapping ring index into the unwrapped total.
+    fn poll_write_pos(&mut self) {
+        let current = self.current_item();
+
+        let delta = if current >= self.last_raw_index {
+            current - self.last_raw_index
+        } else {
+            self.items.len() - self.last_raw_index + current
+        };
+
+        self.write_pos += delta;
+        self.last_raw_index = current;
+    }
+
+    /// Number of whole chunks available to read without blocking.
+    pub fn available(&mut self) -> usize {
+        self.poll_write_pos();
+
+        self.write_pos - self.read_pos
+    }
+
+    /// Copy the next unread chunk into `out`, returning the number of bytes copied (`0` if
+    /// nothing new is available yet).
+    ///
+    /// Returns `Err(CircularError::Overrun)` if the DMA write pointer has lapped the chunks that
+    /// had not been read yet, meaning their contents have already been overwritten. Returns
+    /// `Err(CircularError::OutputTooShort)` if `out` is shorter than a chunk, rather than copying
+    /// a truncated prefix and silently discarding the rest of the chunk.
+    pub fn read(&mut self, out : &mut [u8]) -> Result<usize, CircularError> {
+        let available = self.available();
+
+        if available >= self.items.len() {
+            return Err(CircularError::Overrun);
+        }
+
+        if available == 0 {
+            return Ok(0);
+        }
+
+        if out.len() < self.chunk_len {
+            return Err(CircularError::OutputTooShort);
+        }
+
+        let idx = self.read_pos % self.items.len();
+        let src = self.items[idx].buffer_ptr as *const u8;
+
+        unsafe { core::ptr::copy_nonoverlapping(src, out.as_mut_ptr(), self.chunk_len) };
+
+        self.read_pos += 1;
+
+        Ok(self.chunk_len)
+    }
+}
+
+impl<'a> Drop for CircularReader<'a> {
+    /// Stop the rx channel before this reader's descriptors/buffer can be dropped, so letting a
+    /// `CircularReader` fall out of scope early doesn't leave the DMA engine writing into freed
+    /// memory forever (unlike a one-shot [`Transfer`], a circular ring never completes on its
+    /// own to do this naturally).
+    fn drop(&mut self) {
+        self.pipe.rx_channel.rx_reset(&self.pipe.dma);
+    }
 }
\ No newline at end of file
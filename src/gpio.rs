@@ -3,6 +3,7 @@
 use core::ptr::{read_volatile, write_volatile};
 
 use embedded_hal::digital::{v2::{InputPin, OutputPin, StatefulOutputPin}};
+use esp32c3::{GPIO, IO_MUX};
 
 const GPIO_BASE_ADDR : u32 = 0x6000_4000;
 const IO_MUX_BASE_ADDR : u32 = 0x6000_9000;
@@ -33,6 +34,22 @@ pub enum Pull {
     None,
 }
 
+/// Interrupt trigger condition for a GPIO pin, mirroring the trigger-type field of `CFG_REG`.
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Trigger on a rising edge.
+    RisingEdge = 1,
+    /// Trigger on a falling edge.
+    FallingEdge = 2,
+    /// Trigger on either edge.
+    AnyEdge = 3,
+    /// Trigger while the pin reads low.
+    LowLevel = 4,
+    /// Trigger while the pin reads high.
+    HighLevel = 5,
+}
+
 /// Output pin drive strength
 #[repr(u8)]
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -118,6 +135,15 @@ impl<const S : u32> Pin<S> {
         write_volatile(Self::OUT_CFG_REG as *mut u32, cfg);
     }
 
+    /// Route a peripheral's input `signal` through the GPIO matrix from this pin, by writing
+    /// this pin's number into that signal's `FUNCx_IN_SEL_CFG` register and setting the bit that
+    /// enables matrix routing (as opposed to the default direct/bypassed connection).
+    pub unsafe fn input_select(&self, signal : u8) {
+        let reg = GPIO_BASE_ADDR + 0x154 + 0x4 * signal as u32;
+
+        write_volatile(reg as *mut u32, (S & 0x3F) | (1 << 7));
+    }
+
     pub unsafe fn set_output(&self, high : bool) {
         if high {
             write_volatile(Self::OUT_SET_REG as *mut u32, 1 << S);
@@ -138,22 +164,43 @@ impl<const S : u32> Pin<S> {
 
         write_volatile(Self::IO_MUX as *mut u32, io_mux);
     }
+
+    pub unsafe fn enable_interrupt(&self, event : Event) {
+        let mut cfg = read_volatile(Self::CFG_REG as *const u32);
+
+        cfg &= !((0b111 << 7) | (0b11111 << 13));
+        cfg |= (event as u32) << 7 | 1 << 13;
+
+        write_volatile(Self::CFG_REG as *mut u32, cfg);
+    }
+
+    pub unsafe fn disable_interrupt(&self) {
+        let mut cfg = read_volatile(Self::CFG_REG as *const u32);
+
+        cfg &= !(0b111 << 7);
+
+        write_volatile(Self::CFG_REG as *mut u32, cfg);
+    }
+
+    pub unsafe fn is_pending(&self) -> bool {
+        (read_volatile(Self::IRQS_REG as *const u32) & (1 << S)) > 0
+    }
+
+    pub unsafe fn clear_interrupt(&self) {
+        write_volatile(Self::IRQS_CLR_REG as *mut u32, 1 << S);
+    }
 }
 
 /// Structure to control a pin as an input pin.
-#[derive(Clone, Copy)]
+///
+/// Obtained by calling [`GpioPin::into_input`] on a pin taken from [`Pins`], which guarantees at
+/// compile time that the pin is not configured by any other driver at the same time.
 pub struct Input<const S : u32> {
     pin : Pin<S>
 }
 
 impl<const S : u32> Input<S> {
-    /// Create a new input pin.
-    pub fn new() -> Self {
-
-        debug_assert!(S < 22);
-
-        let pin = Pin{};
-
+    pub(crate) fn from_pin(pin : Pin<S>) -> Self {
         unsafe {
             pin.set_function(GpioFunction::Function1);
             pin.set_pull(Pull::None);
@@ -161,7 +208,7 @@ impl<const S : u32> Input<S> {
             pin.input_enable(true);
         }
 
-        Input { 
+        Input {
             pin
         }
     }
@@ -170,11 +217,25 @@ impl<const S : u32> Input<S> {
     pub fn set_pull(&self, pull : Pull) {
         unsafe{self.pin.set_pull(pull)}
     }
-}
 
-impl<const S : u32> Default for Input<S> {
-    fn default() -> Self {
-        Self::new()
+    /// Configure the pin to raise an interrupt on the given event.
+    pub fn enable_interrupt(&self, event : Event) {
+        unsafe{self.pin.enable_interrupt(event)}
+    }
+
+    /// Stop the pin from raising interrupts.
+    pub fn disable_interrupt(&self) {
+        unsafe{self.pin.disable_interrupt()}
+    }
+
+    /// Check whether this pin has a pending, unacknowledged interrupt.
+    pub fn is_pending(&self) -> bool {
+        unsafe{self.pin.is_pending()}
+    }
+
+    /// Acknowledge and clear a pending interrupt on this pin.
+    pub fn clear_interrupt(&self) {
+        unsafe{self.pin.clear_interrupt()}
     }
 }
 
@@ -191,19 +252,15 @@ impl<const S : u32> InputPin for Input<S> {
 }
 
 /// Structure to control a pin as an output pin.
-#[derive(Clone, Copy)]
+///
+/// Obtained by calling [`GpioPin::into_output`] on a pin taken from [`Pins`], which guarantees at
+/// compile time that the pin is not configured by any other driver at the same time.
 pub struct Output<const S : u32> {
     pin : Pin<S>
 }
 
 impl<const S : u32> Output<S> {
-    /// Create a new input pin.
-    pub fn new() -> Self {
-
-        debug_assert!(S < 22);
-
-        let pin = Pin{};
-
+    pub(crate) fn from_pin(pin : Pin<S>) -> Self {
         unsafe {
             pin.set_function(GpioFunction::Function1);
             pin.output_select(128);
@@ -213,7 +270,7 @@ impl<const S : u32> Output<S> {
             pin.input_enable(false);
         }
 
-        Output { 
+        Output {
             pin
         }
     }
@@ -224,13 +281,6 @@ impl<const S : u32> Output<S> {
     }
 }
 
-impl<const S : u32> Default for Output<S> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-
 impl<const S : u32> OutputPin for Output<S> {
     type Error = ();
 
@@ -253,4 +303,111 @@ impl<const S : u32> StatefulOutputPin for Output<S> {
     fn is_set_low(&self) -> Result<bool, Self::Error> {
         Ok(unsafe{!self.pin.get_output()})
     }
+}
+
+/// An unconfigured, owned GPIO pin, obtained from the [`Pins`] struct returned by [`Gpio::new`].
+///
+/// Converting it into an [`Input`] or [`Output`] consumes it by value, so the compiler rejects
+/// any attempt to configure the same physical pin more than once.
+pub struct GpioPin<const S : u32> {
+    pin : Pin<S>
+}
+
+impl<const S : u32> GpioPin<S> {
+    fn new() -> Self {
+        debug_assert!(S < 22);
+
+        GpioPin { pin : Pin{} }
+    }
+
+    /// Configure this pin as a digital input.
+    pub fn into_input(self) -> Input<S> {
+        Input::from_pin(self.pin)
+    }
+
+    /// Configure this pin as a digital output.
+    pub fn into_output(self) -> Output<S> {
+        Output::from_pin(self.pin)
+    }
+
+    /// Hand this pin over to a peripheral driver so it can route it through the GPIO matrix
+    /// itself (e.g. [`crate::spi::Spi`] wiring up SCK/MOSI/MISO/CS).
+    pub(crate) fn into_peripheral(self) -> Pin<S> {
+        self.pin
+    }
+}
+
+/// Owned handle to the GPIO peripheral. Obtained once from the PAC's `GPIO`/`IO_MUX` tokens and
+/// split into [`Pins`], so every physical pin can be handed out to exactly one driver.
+pub struct Gpio {
+    _gpio : GPIO,
+    _io_mux : IO_MUX,
+}
+
+impl Gpio {
+    /// Take ownership of the `GPIO` and `IO_MUX` peripherals and split them into one owned,
+    /// typed [`GpioPin`] per physical pin.
+    ///
+    /// The `GPIO`/`IO_MUX` singletons are moved into the returned [`Pins`] and never handed back.
+    /// Pins moved out of [`Pins`] hold no reference to it, so they do not keep it alive.
+    pub fn new(gpio : GPIO, io_mux : IO_MUX) -> Pins {
+        let gpio = Gpio { _gpio : gpio, _io_mux : io_mux };
+
+        Pins {
+            _owner : gpio,
+            gpio0 : GpioPin::new(),
+            gpio1 : GpioPin::new(),
+            gpio2 : GpioPin::new(),
+            gpio3 : GpioPin::new(),
+            gpio4 : GpioPin::new(),
+            gpio5 : GpioPin::new(),
+            gpio6 : GpioPin::new(),
+            gpio7 : GpioPin::new(),
+            gpio8 : GpioPin::new(),
+            gpio9 : GpioPin::new(),
+            gpio10 : GpioPin::new(),
+            gpio11 : GpioPin::new(),
+            gpio12 : GpioPin::new(),
+            gpio13 : GpioPin::new(),
+            gpio14 : GpioPin::new(),
+            gpio15 : GpioPin::new(),
+            gpio16 : GpioPin::new(),
+            gpio17 : GpioPin::new(),
+            gpio18 : GpioPin::new(),
+            gpio19 : GpioPin::new(),
+            gpio20 : GpioPin::new(),
+            gpio21 : GpioPin::new(),
+        }
+    }
+}
+
+/// One owned, typed [`GpioPin`] per physical pin, obtained once from [`Gpio::new`].
+///
+/// Holds the [`Gpio`] peripheral handle that backs these pins; it is dropped together with this
+/// struct, whether or not pins have been moved out of it.
+#[allow(missing_docs)]
+pub struct Pins {
+    _owner : Gpio,
+    pub gpio0 : GpioPin<0>,
+    pub gpio1 : GpioPin<1>,
+    pub gpio2 : GpioPin<2>,
+    pub gpio3 : GpioPin<3>,
+    pub gpio4 : GpioPin<4>,
+    pub gpio5 : GpioPin<5>,
+    pub gpio6 : GpioPin<6>,
+    pub gpio7 : GpioPin<7>,
+    pub gpio8 : GpioPin<8>,
+    pub gpio9 : GpioPin<9>,
+    pub gpio10 : GpioPin<10>,
+    pub gpio11 : GpioPin<11>,
+    pub gpio12 : GpioPin<12>,
+    pub gpio13 : GpioPin<13>,
+    pub gpio14 : GpioPin<14>,
+    pub gpio15 : GpioPin<15>,
+    pub gpio16 : GpioPin<16>,
+    pub gpio17 : GpioPin<17>,
+    pub gpio18 : GpioPin<18>,
+    pub gpio19 : GpioPin<19>,
+    pub gpio20 : GpioPin<20>,
+    pub gpio21 : GpioPin<21>,
 }
\ No newline at end of file
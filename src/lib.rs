@@ -8,6 +8,10 @@ pub use embedded_hal as hal;
 
 pub mod gpio;
 pub mod dma;
+pub mod spi;
+
+#[cfg(feature = "aes")]
+pub mod aes;
 
 #[cfg(feature = "rt")]
 pub use esp32c_rt;
\ No newline at end of file
@@ -0,0 +1,277 @@
+//! Driver for the SPI2 peripheral
+
+use core::ptr::{read_volatile, write_volatile};
+
+use hal::blocking::spi::{Transfer, Write};
+use hal::spi::{Phase, Polarity};
+
+use crate::dma::{Channel, DMAPipe, ListItem, Peripheral};
+use crate::gpio::{GpioFunction, GpioPin, Pin};
+
+const SPI2_BASE_ADDR : u32 = 0x6002_A000;
+
+/// Frequency of the APB clock the SPI clock divider is derived from.
+const APB_CLK_HZ : u32 = 80_000_000;
+
+/// Maximum number of bytes a single `ListItem` can describe.
+const MAX_CHUNK : usize = 4095;
+
+/// Size, in bytes, of each DMA sub-transfer both [`Write::write`] and [`Transfer::transfer`]
+/// split their input into before handing it to [`Spi::full_duplex`], and the size of every stack
+/// buffer involved (`full_duplex`'s own tx copy included). Kept well below [`MAX_CHUNK`] — the
+/// hardware's much larger per-descriptor limit — so no single call ever needs to reserve
+/// anywhere near the worst case just to move a handful of bytes.
+const WRITE_CHUNK : usize = 256;
+
+/// GPIO matrix signal numbers used to route `Spi`'s pins. These identify the SPI2 (FSPI)
+/// peripheral's signals in the GPIO matrix, independent of which physical pin they end up wired
+/// to.
+const SCK_OUT_SIGNAL : u8 = 63;
+const MOSI_OUT_SIGNAL : u8 = 65;
+const MISO_IN_SIGNAL : u8 = 64;
+const CS_OUT_SIGNAL : u8 = 68;
+
+/// Bit order used to shift data in and out of the bus.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Most significant bit first.
+    MsbFirst,
+    /// Least significant bit first.
+    LsbFirst,
+}
+
+/// Configuration used to set up the SPI bus.
+#[derive(Clone, Copy)]
+pub struct Config {
+    /// Target SCK frequency in Hz.
+    pub frequency : u32,
+    /// Clock polarity.
+    pub polarity : Polarity,
+    /// Clock phase.
+    pub phase : Phase,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            frequency : 1_000_000,
+            polarity : Polarity::IdleLow,
+            phase : Phase::CaptureOnFirstTransition,
+        }
+    }
+}
+
+/// Error produced by the SPI driver.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// A single DMA sub-transfer was longer than the driver's internal chunk size.
+    BufferTooLarge,
+}
+
+/// Driver for the SPI2 peripheral, using a `DMAPipe` for full-duplex transfers.
+pub struct Spi {
+    pipe : DMAPipe,
+}
+
+impl Spi {
+    const CMD_REG : u32 = SPI2_BASE_ADDR;
+    const CTRL_REG : u32 = SPI2_BASE_ADDR + 0x08;
+    const CLOCK_REG : u32 = SPI2_BASE_ADDR + 0x14;
+    const USER_REG : u32 = SPI2_BASE_ADDR + 0x18;
+    const MS_DLEN_REG : u32 = SPI2_BASE_ADDR + 0x24;
+    const DMA_CONF_REG : u32 = SPI2_BASE_ADDR + 0x2C;
+
+    /// Configure and take ownership of the SPI2 peripheral, routing `sck`/`mosi`/`miso`/`cs`
+    /// through the GPIO matrix and driving transfers through the given DMA channels.
+    pub fn new<const SCK : u32, const MOSI : u32, const MISO : u32, const CS : u32>(
+        sck : GpioPin<SCK>,
+        mosi : GpioPin<MOSI>,
+        miso : GpioPin<MISO>,
+        cs : GpioPin<CS>,
+        tx_channel : Channel,
+        rx_channel : Channel,
+        config : Config,
+    ) -> Self {
+        Self::route_output(sck.into_peripheral(), SCK_OUT_SIGNAL);
+        Self::route_output(mosi.into_peripheral(), MOSI_OUT_SIGNAL);
+        Self::route_output(cs.into_peripheral(), CS_OUT_SIGNAL);
+        Self::route_input(miso.into_peripheral(), MISO_IN_SIGNAL);
+
+        let pipe = DMAPipe::memory_n_peripheral(tx_channel, rx_channel, Peripheral::SPI2);
+
+        let spi = Spi { pipe };
+
+        spi.set_clock(config.frequency);
+        spi.set_mode(config.polarity, config.phase);
+        spi.set_bit_order(BitOrder::MsbFirst);
+
+        spi
+    }
+
+    /// Route a pin to drive one of SPI2's output signals (SCK/MOSI/CS) through the GPIO matrix.
+    fn route_output<const S : u32>(pin : Pin<S>, signal : u8) {
+        unsafe {
+            pin.set_function(GpioFunction::Function1);
+            pin.output_select(signal);
+            pin.output_enable(true);
+            pin.input_enable(false);
+        }
+    }
+
+    /// Route a pin to feed one of SPI2's input signals (MISO) through the GPIO matrix.
+    fn route_input<const S : u32>(pin : Pin<S>, signal : u8) {
+        unsafe {
+            pin.set_function(GpioFunction::Function1);
+            pin.input_select(signal);
+            pin.input_enable(true);
+            pin.output_enable(false);
+        }
+    }
+
+    /// Set the SCK frequency, picking the smallest integer divider of the APB clock that does
+    /// not exceed the requested frequency.
+    pub fn set_clock(&self, frequency : u32) {
+        let frequency = frequency.max(1);
+        let divider = (APB_CLK_HZ / frequency + (APB_CLK_HZ % frequency != 0) as u32).max(1);
+
+        // `CLOCK_REG` only ever holds clock configuration, so it is rebuilt from scratch rather
+        // than read-modify-written; that also makes sure the bypass bit (31) is always cleared
+        // when it doesn't apply, instead of sticking from a previous call.
+        let clock = if divider <= 1 {
+            1 << 31
+        } else {
+            let pre = divider - 1;
+            let half = divider / 2;
+
+            (pre & 0x1FFF) << 18
+                | ((divider - 1) & 0x3F) << 12
+                | (half.saturating_sub(1) & 0x3F) << 6
+                | (divider - 1) & 0x3F
+        };
+
+        unsafe { write_volatile(Self::CLOCK_REG as *mut u32, clock) };
+    }
+
+    /// Set the clock polarity and phase of the bus.
+    pub fn set_mode(&self, polarity : Polarity, phase : Phase) {
+        let mut ctrl = unsafe { read_volatile(Self::CTRL_REG as *const u32) };
+
+        match polarity {
+            Polarity::IdleLow => ctrl &= !(1 << 29),
+            Polarity::IdleHigh => ctrl |= 1 << 29,
+        }
+
+        unsafe { write_volatile(Self::CTRL_REG as *mut u32, ctrl) };
+
+        let mut user = unsafe { read_volatile(Self::USER_REG as *const u32) };
+
+        match phase {
+            Phase::CaptureOnFirstTransition => user &= !(1 << 28),
+            Phase::CaptureOnSecondTransition => user |= 1 << 28,
+        }
+
+        unsafe { write_volatile(Self::USER_REG as *mut u32, user) };
+    }
+
+    /// Set the bit order data is shifted in and out of the bus with.
+    pub fn set_bit_order(&self, order : BitOrder) {
+        let mut ctrl = unsafe { read_volatile(Self::CTRL_REG as *const u32) };
+
+        match order {
+            BitOrder::MsbFirst => ctrl &= !(0b11 << 14),
+            BitOrder::LsbFirst => ctrl |= 0b11 << 14,
+        }
+
+        unsafe { write_volatile(Self::CTRL_REG as *mut u32, ctrl) };
+    }
+
+    /// Start the peripheral running on the command register.
+    fn start(&self) {
+        unsafe { write_volatile(Self::CMD_REG as *mut u32, 1 << 18) };
+    }
+
+    /// Enable the peripheral's own DMA data path and program the transfer length, in bits, for
+    /// an upcoming `len`-byte transfer. Without this, the DMA channel moves data to/from memory
+    /// but the SPI peripheral itself never reads it from (or feeds it into) its FIFO.
+    fn configure_dma(&self, len : usize) {
+        let bits = (len * 8).saturating_sub(1) as u32;
+
+        unsafe { write_volatile(Self::MS_DLEN_REG as *mut u32, bits) };
+
+        let mut dma_conf = unsafe { read_volatile(Self::DMA_CONF_REG as *const u32) };
+
+        dma_conf |= (1 << 3) | (1 << 4);
+
+        unsafe { write_volatile(Self::DMA_CONF_REG as *mut u32, dma_conf) };
+    }
+
+    /// Run a full-duplex transfer, sending `words` and overwriting it with the data received
+    /// back from the bus.
+    ///
+    /// `words` must fit in a single chunk (see [`WRITE_CHUNK`]); both [`Transfer::transfer`] and
+    /// [`Write::write`] split larger buffers into `WRITE_CHUNK`-sized pieces before calling this,
+    /// so its own stack buffer only ever needs to cover one chunk, not the hardware's much larger
+    /// [`MAX_CHUNK`] per-descriptor limit.
+    fn full_duplex(&mut self, words : &mut [u8]) -> Result<(), Error> {
+        debug_assert!(WRITE_CHUNK <= MAX_CHUNK);
+
+        if words.len() > WRITE_CHUNK {
+            return Err(Error::BufferTooLarge);
+        }
+
+        let mut tx_copy = [0u8; WRITE_CHUNK];
+        tx_copy[..words.len()].copy_from_slice(words);
+
+        let mut tx_item = ListItem::new();
+        tx_item.set_buffer::<u8>(&mut tx_copy[..words.len()]);
+
+        let mut rx_item = ListItem::new();
+        rx_item.set_buffer::<u8>(words);
+
+        self.configure_dma(words.len());
+        self.pipe.start_transfer(&tx_item, &rx_item);
+        self.start();
+
+        while !(self.pipe.get_tx_completion() && self.pipe.get_rx_completion()) {}
+
+        Ok(())
+    }
+}
+
+impl Transfer<u8> for Spi {
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words : &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        let mut offset = 0;
+
+        while offset < words.len() {
+            let len = (words.len() - offset).min(WRITE_CHUNK);
+
+            self.full_duplex(&mut words[offset..offset + len])?;
+
+            offset += len;
+        }
+
+        Ok(words)
+    }
+}
+
+impl Write<u8> for Spi {
+    type Error = Error;
+
+    fn write(&mut self, words : &[u8]) -> Result<(), Self::Error> {
+        let mut scratch = [0u8; WRITE_CHUNK];
+        let mut remaining = words;
+
+        while !remaining.is_empty() {
+            let len = remaining.len().min(WRITE_CHUNK);
+
+            scratch[..len].copy_from_slice(&remaining[..len]);
+            self.full_duplex(&mut scratch[..len])?;
+
+            remaining = &remaining[len..];
+        }
+
+        Ok(())
+    }
+}